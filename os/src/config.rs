@@ -0,0 +1,21 @@
+//! Constants used in rCore
+
+#[allow(unused)]
+pub const USER_STACK_SIZE: usize = 4096 * 2;
+#[allow(unused)]
+pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+pub const KERNEL_HEAP_SIZE: usize = 0x20_0000;
+
+pub const MEMORY_END: usize = 0x8800_0000;
+
+pub const PAGE_SIZE: usize = 0x1000;
+pub const PAGE_SIZE_BITS: usize = 0xc;
+
+pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
+pub const TRAP_CONTEXT_BASE: usize = TRAMPOLINE - PAGE_SIZE;
+
+/// Maximum number of distinct syscalls tracked per task, e.g. by
+/// `TaskInfoInner::syscall_times` and the seccomp-style filter bitmap.
+pub const MAX_SYSCALL_NUM: usize = 500;
+
+pub const CLOCK_FREQ: usize = 12500000;
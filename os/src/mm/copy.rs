@@ -0,0 +1,38 @@
+//! Page-boundary-safe copies between kernel and user memory.
+//!
+//! `translated_ptr`/`translated_refmut` assume the pointee lives entirely
+//! within one physical frame; a `TimeVal` or `TaskInfo` landing across a
+//! page boundary would get silently corrupted. These two helpers instead
+//! go through `translated_byte_buffer`, which already splits an arbitrary
+//! byte range at page boundaries, and stitch the value's bytes across
+//! however many frames that range spans.
+
+use super::page_table::translated_byte_buffer;
+use core::mem::size_of;
+
+/// Copies `*value` into the `size_of::<T>()` bytes at `dst` in the user
+/// address space identified by `token`, splitting the write across page
+/// boundaries as needed.
+pub fn copy_to_user<T: 'static + Copy>(token: usize, dst: *mut T, value: &T) {
+    let src = unsafe { core::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) };
+    let mut offset = 0;
+    for chunk in translated_byte_buffer(token, dst as *const u8, size_of::<T>()) {
+        let len = chunk.len();
+        chunk.copy_from_slice(&src[offset..offset + len]);
+        offset += len;
+    }
+}
+
+/// Reads a `T` out of the user address space identified by `token`,
+/// starting at `src`, stitching the bytes together across however many
+/// pages they span.
+pub fn copy_from_user<T: 'static + Copy>(token: usize, src: *const T) -> T {
+    let mut buf = alloc::vec![0u8; size_of::<T>()];
+    let mut offset = 0;
+    for chunk in translated_byte_buffer(token, src as *const u8, size_of::<T>()) {
+        let len = chunk.len();
+        buf[offset..offset + len].copy_from_slice(chunk);
+        offset += len;
+    }
+    unsafe { (buf.as_ptr() as *const T).read_unaligned() }
+}
@@ -0,0 +1,150 @@
+//! Implementation of a stack-based frame allocator, extended with a
+//! per-frame reference count so copy-on-write mappings can share a frame
+//! across processes.
+
+use super::address::{PhysAddr, PhysPageNum};
+use crate::config::MEMORY_END;
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Formatter};
+use lazy_static::*;
+
+/// Tracks ownership of a single physical frame. Dropping the last
+/// `FrameTracker` over a frame returns it to the allocator.
+pub struct FrameTracker {
+    pub ppn: PhysPageNum,
+}
+
+impl FrameTracker {
+    pub fn new(ppn: PhysPageNum) -> Self {
+        let bytes_array = ppn.get_bytes_array();
+        for i in bytes_array {
+            *i = 0;
+        }
+        Self { ppn }
+    }
+
+    /// Wraps a frame this caller already holds a freshly-incremented
+    /// reference to (via `frame_add_ref`), without zeroing its contents.
+    /// Used when a copy-on-write mapping shares an existing, already-live
+    /// frame instead of allocating a new one.
+    pub fn shared(ppn: PhysPageNum) -> Self {
+        Self { ppn }
+    }
+}
+
+impl Debug for FrameTracker {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("FrameTracker:PPN={:#x}", self.ppn.0))
+    }
+}
+
+impl Drop for FrameTracker {
+    fn drop(&mut self) {
+        frame_dealloc(self.ppn);
+    }
+}
+
+trait FrameAllocatorTrait {
+    fn new() -> Self;
+    fn alloc(&mut self) -> Option<PhysPageNum>;
+    fn dealloc(&mut self, ppn: PhysPageNum);
+}
+
+pub struct StackFrameAllocator {
+    current: usize,
+    end: usize,
+    recycled: Vec<usize>,
+    /// Reference count for every frame handed out. Frames shared by a
+    /// copy-on-write mapping have `refcount > 1`; the fault handler
+    /// decrements it and only copies if it was still shared.
+    refcount: Vec<usize>,
+}
+
+impl StackFrameAllocator {
+    pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.current = l.0;
+        self.end = r.0;
+        self.refcount = alloc::vec![0usize; self.end];
+    }
+}
+impl FrameAllocatorTrait for StackFrameAllocator {
+    fn new() -> Self {
+        Self {
+            current: 0,
+            end: 0,
+            recycled: Vec::new(),
+            refcount: Vec::new(),
+        }
+    }
+    fn alloc(&mut self) -> Option<PhysPageNum> {
+        let ppn = if let Some(ppn) = self.recycled.pop() {
+            ppn
+        } else if self.current == self.end {
+            return None;
+        } else {
+            self.current += 1;
+            self.current - 1
+        };
+        self.refcount[ppn] = 1;
+        Some(ppn.into())
+    }
+    /// Drops one reference to `ppn`. The frame only actually returns to
+    /// `recycled` once its refcount reaches zero — until then some other
+    /// copy-on-write mapping still legitimately points at it, and freeing
+    /// it here would let the allocator hand the same physical page to an
+    /// unrelated caller while that mapping is still live.
+    fn dealloc(&mut self, ppn: PhysPageNum) {
+        let idx = ppn.0;
+        if idx >= self.current || self.recycled.iter().any(|v| *v == idx) {
+            panic!("Frame ppn={:#x} has not been allocated!", idx);
+        }
+        assert!(self.refcount[idx] > 0, "Frame ppn={:#x} over-decremented!", idx);
+        self.refcount[idx] -= 1;
+        if self.refcount[idx] == 0 {
+            self.recycled.push(idx);
+        }
+    }
+}
+
+type FrameAllocatorImpl = StackFrameAllocator;
+
+lazy_static! {
+    pub static ref FRAME_ALLOCATOR: UPSafeCell<FrameAllocatorImpl> =
+        unsafe { UPSafeCell::new(FrameAllocatorImpl::new()) };
+}
+
+pub fn init_frame_allocator() {
+    extern "C" {
+        fn ekernel();
+    }
+    FRAME_ALLOCATOR.exclusive_access().init(
+        PhysAddr::from(ekernel as usize).ceil(),
+        PhysAddr::from(MEMORY_END).floor(),
+    );
+}
+
+pub fn frame_alloc() -> Option<FrameTracker> {
+    FRAME_ALLOCATOR
+        .exclusive_access()
+        .alloc()
+        .map(FrameTracker::new)
+}
+
+pub fn frame_dealloc(ppn: PhysPageNum) {
+    FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
+}
+
+/// Bumps the reference count of an existing frame. Called when a
+/// copy-on-write fork maps a child PTE onto a frame the parent still owns.
+pub fn frame_add_ref(ppn: PhysPageNum) {
+    FRAME_ALLOCATOR.exclusive_access().refcount[ppn.0] += 1;
+}
+
+/// Current reference count of a frame. A COW store-fault handler uses
+/// this to decide whether it needs to copy (refcount `> 1`, some other
+/// process still shares the frame) or may just reclaim it in place
+/// (refcount `1`, the faulting process is the sole remaining owner).
+pub fn frame_ref_count(ppn: PhysPageNum) -> usize {
+    FRAME_ALLOCATOR.exclusive_access().refcount[ppn.0]
+}
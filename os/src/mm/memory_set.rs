@@ -0,0 +1,339 @@
+//! Implementation of `MapArea` and `MemorySet`.
+
+use super::address::{PhysPageNum, VPNRange, VirtAddr, VirtPageNum};
+use super::frame_allocator::{frame_add_ref, frame_alloc, frame_ref_count, FrameTracker};
+use super::page_table::{PTEFlags, PageTable};
+use alloc::collections::BTreeMap;
+use bitflags::*;
+
+bitflags! {
+    pub struct MapPermission: u8 {
+        const R = 1 << 1;
+        const W = 1 << 2;
+        const X = 1 << 3;
+        const U = 1 << 4;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MapType {
+    Identical,
+    Framed,
+}
+
+/// A contiguous, uniformly-permissioned run of virtual pages — the unit
+/// `MemorySet` tracks areas in, analogous to a `vm_area_struct`.
+pub struct MapArea {
+    pub vpn_range: VPNRange,
+    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    map_type: MapType,
+    pub map_perm: MapPermission,
+}
+
+impl MapArea {
+    pub fn new(
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        map_type: MapType,
+        map_perm: MapPermission,
+    ) -> Self {
+        let start_vpn = start_va.floor();
+        let end_vpn = end_va.ceil();
+        Self {
+            vpn_range: VPNRange::new(start_vpn, end_vpn),
+            data_frames: BTreeMap::new(),
+            map_type,
+            map_perm,
+        }
+    }
+
+    /// Clones the *shape* of an existing framed area without copying data;
+    /// used as the basis for the copy-on-write sharing path in
+    /// `MemorySet::from_existed_user`.
+    pub fn from_another(another: &Self) -> Self {
+        Self {
+            vpn_range: VPNRange::new(another.vpn_range.get_start(), another.vpn_range.get_end()),
+            data_frames: BTreeMap::new(),
+            map_type: another.map_type,
+            map_perm: another.map_perm,
+        }
+    }
+
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        let ppn: PhysPageNum;
+        match self.map_type {
+            MapType::Identical => {
+                ppn = PhysPageNum(vpn.0);
+            }
+            MapType::Framed => {
+                let frame = frame_alloc().unwrap();
+                ppn = frame.ppn;
+                self.data_frames.insert(vpn, frame);
+            }
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits() as u16).unwrap();
+        page_table.map(vpn, ppn, pte_flags);
+    }
+
+    pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        if self.map_type == MapType::Framed {
+            self.data_frames.remove(&vpn);
+        }
+        page_table.unmap(vpn);
+    }
+
+    pub fn map(&mut self, page_table: &mut PageTable) {
+        for vpn in self.vpn_range {
+            self.map_one(page_table, vpn);
+        }
+    }
+
+    pub fn unmap(&mut self, page_table: &mut PageTable) {
+        for vpn in self.vpn_range {
+            self.unmap_one(page_table, vpn);
+        }
+    }
+
+    /// Splits this area in two at `at` (which must fall strictly inside its
+    /// range), shrinking `self` down to `[start, at)` and returning a new
+    /// `MapArea` covering `[at, end)`. The returned area's frames are the
+    /// same `FrameTracker`s, just handed off — no pages are unmapped or
+    /// reallocated. Used by `MemorySet::munmap` to carve a hole out of the
+    /// middle of a mapping without touching the rest of it.
+    pub fn split_off(&mut self, at: VirtPageNum) -> Self {
+        let start = self.vpn_range.get_start();
+        let end = self.vpn_range.get_end();
+        assert!(start < at && at < end, "split point must be strictly interior");
+        let right_frames = self.data_frames.split_off(&at);
+        self.vpn_range = VPNRange::new(start, at);
+        Self {
+            vpn_range: VPNRange::new(at, end),
+            data_frames: right_frames,
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+        }
+    }
+
+    /// Maps every page of this area onto the *same* frames as `parent`,
+    /// marking writable mappings copy-on-write in both page tables and
+    /// bumping the shared frame's reference count. Read-only/executable
+    /// pages (e.g. `.text`) are simply shared, since nobody ever writes
+    /// them. Every shared frame gets its own `FrameTracker` in this area's
+    /// `data_frames`, alongside the parent's, so each side's `Drop` only
+    /// releases its own share instead of one side silently owning the
+    /// only live tracker.
+    pub fn map_cow(&mut self, page_table: &mut PageTable, parent_pt: &mut PageTable) {
+        for vpn in self.vpn_range {
+            let parent_pte = parent_pt.find_pte(vpn).unwrap();
+            let ppn = parent_pte.ppn();
+            let writable = self.map_perm.contains(MapPermission::W);
+            let mut flags = PTEFlags::from_bits(self.map_perm.bits() as u16).unwrap();
+            if writable {
+                flags.remove(PTEFlags::W);
+                flags.insert(PTEFlags::COW);
+                parent_pte.set_cow();
+            }
+            if self.map_type == MapType::Framed {
+                frame_add_ref(ppn);
+                self.data_frames.insert(vpn, FrameTracker::shared(ppn));
+            }
+            page_table.map(vpn, ppn, flags);
+        }
+    }
+}
+
+pub struct MemorySet {
+    page_table: PageTable,
+    areas: alloc::vec::Vec<MapArea>,
+}
+
+impl MemorySet {
+    pub fn new_bare() -> Self {
+        Self {
+            page_table: PageTable::new(),
+            areas: alloc::vec::Vec::new(),
+        }
+    }
+
+    pub fn token(&self) -> usize {
+        self.page_table.token()
+    }
+
+    pub fn insert_framed_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) {
+        self.push(
+            MapArea::new(start_va, end_va, MapType::Framed, permission),
+            None,
+        );
+    }
+
+    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
+        map_area.map(&mut self.page_table);
+        if let Some(data) = data {
+            self.copy_data(&map_area, data);
+        }
+        self.areas.push(map_area);
+    }
+
+    fn copy_data(&mut self, area: &MapArea, data: &[u8]) {
+        let mut start: usize = 0;
+        let mut current_vpn = area.vpn_range.get_start();
+        let len = data.len();
+        loop {
+            let src = &data[start..len.min(start + crate::config::PAGE_SIZE)];
+            let dst = &mut self
+                .page_table
+                .translate(current_vpn)
+                .unwrap()
+                .ppn()
+                .get_bytes_array()[..src.len()];
+            dst.copy_from_slice(src);
+            start += crate::config::PAGE_SIZE;
+            if start >= len {
+                break;
+            }
+            current_vpn.0 += 1;
+        }
+    }
+
+    /// Builds the child's address space for a copy-on-write `fork`: every
+    /// writable framed area is mapped onto the parent's existing frames
+    /// (read-only + `COW`), rather than allocated and copied up front. The
+    /// actual copy happens lazily, on the child's first store fault.
+    pub fn from_existed_user(user_space: &mut MemorySet) -> Self {
+        let mut memory_set = Self::new_bare();
+        for area in user_space.areas.iter() {
+            let mut new_area = MapArea::from_another(area);
+            new_area.map_cow(&mut memory_set.page_table, &mut user_space.page_table);
+            memory_set.areas.push(new_area);
+        }
+        memory_set
+    }
+
+    /// Handles a store page fault at `vpn`. Returns `true` if it was a COW
+    /// fault and has been resolved (page is now writable), `false` if
+    /// `vpn` is not a COW mapping and the fault must be treated as fatal by
+    /// the caller.
+    pub fn handle_cow_fault(&mut self, vpn: VirtPageNum) -> bool {
+        let pte = match self.page_table.find_pte(vpn) {
+            Some(pte) if pte.is_cow() => pte,
+            _ => return false,
+        };
+        let old_ppn = pte.ppn();
+        if frame_ref_count(old_ppn) == 1 {
+            // We're the sole remaining owner (every other process sharing
+            // this frame has already dropped its reference) — just
+            // reclaim write permission on the same frame, no copy needed.
+            pte.clear_cow();
+            return true;
+        }
+        let new_frame = frame_alloc().unwrap();
+        new_frame
+            .ppn
+            .get_bytes_array()
+            .copy_from_slice(old_ppn.get_bytes_array());
+        let new_ppn = new_frame.ppn;
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|a| a.vpn_range.contains(vpn))
+            .expect("COW PTE exists with no owning MapArea");
+        // Replacing this area's own tracker drops its share of `old_ppn`,
+        // which is exactly the "give up the shared frame for a private
+        // copy" half of copy-on-write; the refcount only really frees the
+        // frame once every sharer has done this. This must happen before
+        // the PTE is repointed below — otherwise, if no area claimed
+        // `new_frame`, it would drop (and its physical frame could be
+        // reallocated to someone else) while the PTE still pointed at it.
+        area.data_frames.insert(vpn, new_frame);
+        pte.set_ppn(new_ppn);
+        pte.clear_cow();
+        true
+    }
+
+    fn is_mapped(&self, vpn: VirtPageNum) -> bool {
+        self.page_table
+            .translate(vpn)
+            .map_or(false, |pte| pte.is_valid())
+    }
+
+    /// Maps `[start_vpn, end_vpn)` as a fresh, page-permission-checked VMA.
+    /// `port` holds R/W/X in its low three bits, Starnix/Linux `mmap`
+    /// style. Returns `-2` if any page in the range is already mapped,
+    /// `0` on success.
+    pub fn mmap(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum, port: usize) -> isize {
+        for vpn in VPNRange::new(start_vpn, end_vpn) {
+            if self.is_mapped(vpn) {
+                return -2;
+            }
+        }
+        let perm = MapPermission::from_bits(((port as u8) & 0x7) << 1).unwrap() | MapPermission::U;
+        let area = MapArea::new(start_vpn.into(), end_vpn.into(), MapType::Framed, perm);
+        self.insert_area(area);
+        0
+    }
+
+    /// Unmaps `[start_vpn, end_vpn)`, splitting any `MapArea` this range
+    /// falls in the middle of into up to two remainder areas. Returns `-3`
+    /// if any page in the range is not currently mapped; otherwise every
+    /// page in the range is unmapped and `0` is returned.
+    pub fn munmap(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> isize {
+        for vpn in VPNRange::new(start_vpn, end_vpn) {
+            if !self.is_mapped(vpn) {
+                return -3;
+            }
+        }
+        let mut remaining = alloc::vec::Vec::new();
+        for mut area in core::mem::take(&mut self.areas) {
+            let a_start = area.vpn_range.get_start();
+            let a_end = area.vpn_range.get_end();
+            if a_end <= start_vpn || a_start >= end_vpn {
+                // No overlap with the unmapped range at all.
+                remaining.push(area);
+                continue;
+            }
+            let overlap_start = if start_vpn > a_start { start_vpn } else { a_start };
+            let overlap_end = if end_vpn < a_end { end_vpn } else { a_end };
+            if overlap_start <= a_start && overlap_end >= a_end {
+                // The whole area falls inside the unmapped range.
+                area.unmap(&mut self.page_table);
+            } else if overlap_start > a_start && overlap_end < a_end {
+                // The hole is strictly interior: split off both sides.
+                let right = area.split_off(overlap_end);
+                let mut middle = area.split_off(overlap_start);
+                middle.unmap(&mut self.page_table);
+                remaining.push(area);
+                remaining.push(right);
+            } else if overlap_start == a_start {
+                // A prefix of the area is unmapped.
+                let remainder = area.split_off(overlap_end);
+                area.unmap(&mut self.page_table);
+                remaining.push(remainder);
+            } else {
+                // A suffix of the area is unmapped.
+                let mut suffix = area.split_off(overlap_start);
+                suffix.unmap(&mut self.page_table);
+                remaining.push(area);
+            }
+        }
+        remaining.sort_by_key(|a| a.vpn_range.get_start());
+        self.areas = remaining;
+        0
+    }
+
+    /// Inserts `area` keeping `self.areas` sorted by start address, as a
+    /// real VMA list would be, so overlap checks and lookups stay cheap.
+    fn insert_area(&mut self, mut area: MapArea) {
+        area.map(&mut self.page_table);
+        let pos = self
+            .areas
+            .iter()
+            .position(|a| a.vpn_range.get_start().0 > area.vpn_range.get_start().0)
+            .unwrap_or(self.areas.len());
+        self.areas.insert(pos, area);
+    }
+}
@@ -0,0 +1,21 @@
+//! Memory management: address spaces, page tables, and the frame
+//! allocator underneath them.
+
+mod address;
+mod copy;
+mod frame_allocator;
+mod memory_set;
+mod page_table;
+
+pub use address::{PhysAddr, PhysPageNum, VPNRange, VirtAddr, VirtPageNum};
+pub use copy::{copy_from_user, copy_to_user};
+pub use frame_allocator::{frame_alloc, frame_add_ref, frame_dealloc, frame_ref_count, FrameTracker};
+pub use memory_set::{MapArea, MapPermission, MapType, MemorySet};
+pub use page_table::{
+    translated_byte_buffer, translated_ptr, translated_ref, translated_refmut, translated_str,
+    PTEFlags, PageTable, PageTableEntry,
+};
+
+pub fn init() {
+    frame_allocator::init_frame_allocator();
+}
@@ -0,0 +1,254 @@
+//! Implementation of the Sv39 page table and the user-pointer translation
+//! helpers built on top of it.
+
+use super::address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
+use super::frame_allocator::{frame_alloc, FrameTracker};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use bitflags::*;
+
+bitflags! {
+    pub struct PTEFlags: u16 {
+        const V = 1 << 0;
+        const R = 1 << 1;
+        const W = 1 << 2;
+        const X = 1 << 3;
+        const U = 1 << 4;
+        const G = 1 << 5;
+        const A = 1 << 6;
+        const D = 1 << 7;
+        // Software-defined bit, carved out of the RSW (reserved-for-software)
+        // field: set on a mapping whose frame is shared copy-on-write.
+        // Set, while `W` is cleared, when the page is mapped read-only for
+        // COW; the store-fault handler clears it (and restores `W`) once the
+        // page is privately owned.
+        const COW = 1 << 8;
+    }
+}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct PageTableEntry {
+    pub bits: usize,
+}
+
+impl PageTableEntry {
+    pub fn new(ppn: PhysPageNum, flags: PTEFlags) -> Self {
+        Self {
+            bits: ppn.0 << 10 | flags.bits as usize,
+        }
+    }
+    pub fn empty() -> Self {
+        Self { bits: 0 }
+    }
+    pub fn ppn(&self) -> PhysPageNum {
+        (self.bits >> 10 & ((1usize << 44) - 1)).into()
+    }
+    pub fn flags(&self) -> PTEFlags {
+        PTEFlags::from_bits_truncate(self.bits as u16)
+    }
+    pub fn is_valid(&self) -> bool {
+        (self.flags() & PTEFlags::V) != PTEFlags::empty()
+    }
+    pub fn is_cow(&self) -> bool {
+        (self.flags() & PTEFlags::COW) != PTEFlags::empty()
+    }
+    pub fn writable(&self) -> bool {
+        (self.flags() & PTEFlags::W) != PTEFlags::empty()
+    }
+    /// Clears `W` and sets `COW`; used when a fork shares a writable frame
+    /// between parent and child.
+    pub fn set_cow(&mut self) {
+        let ppn = self.ppn();
+        let flags = (self.flags() - PTEFlags::W) | PTEFlags::COW;
+        *self = Self::new(ppn, flags);
+    }
+    /// Clears `COW` and sets `W`; used once a store fault has given this
+    /// PTE a privately-owned frame.
+    pub fn clear_cow(&mut self) {
+        let ppn = self.ppn();
+        let flags = (self.flags() - PTEFlags::COW) | PTEFlags::W;
+        *self = Self::new(ppn, flags);
+    }
+    /// Repoints this PTE at `ppn` while keeping its flags, used by the COW
+    /// fault handler after it allocates and copies into a fresh frame.
+    pub fn set_ppn(&mut self, ppn: PhysPageNum) {
+        *self = Self::new(ppn, self.flags());
+    }
+}
+
+/// A three-level Sv39 page table, owning the frames of its intermediate
+/// nodes via `frames` (leaf/data frames are owned elsewhere, e.g. by a
+/// `MapArea`).
+pub struct PageTable {
+    root_ppn: PhysPageNum,
+    frames: Vec<FrameTracker>,
+}
+
+impl PageTable {
+    pub fn new() -> Self {
+        let frame = frame_alloc().unwrap();
+        Self {
+            root_ppn: frame.ppn,
+            frames: vec![frame],
+        }
+    }
+
+    /// Builds a `PageTable` that borrows the `satp` token's root page table
+    /// without owning any of its frames; used to reach into a *different*
+    /// process's address space (e.g. to translate a user pointer).
+    pub fn from_token(satp: usize) -> Self {
+        Self {
+            root_ppn: PhysPageNum::from(satp & ((1usize << 44) - 1)),
+            frames: Vec::new(),
+        }
+    }
+
+    fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        let mut result: Option<&mut PageTableEntry> = None;
+        for (i, idx) in idxs.iter().enumerate() {
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 {
+                result = Some(pte);
+                break;
+            }
+            if !pte.is_valid() {
+                let frame = frame_alloc().unwrap();
+                *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
+                self.frames.push(frame);
+            }
+            ppn = pte.ppn();
+        }
+        result
+    }
+
+    pub fn find_pte(&self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        let mut result: Option<&mut PageTableEntry> = None;
+        for (i, idx) in idxs.iter().enumerate() {
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 {
+                result = Some(pte);
+                break;
+            }
+            if !pte.is_valid() {
+                return None;
+            }
+            ppn = pte.ppn();
+        }
+        result
+    }
+
+    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
+
+    pub fn unmap(&mut self, vpn: VirtPageNum) {
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
+        *pte = PageTableEntry::empty();
+    }
+
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
+        self.find_pte(vpn).map(|pte| *pte)
+    }
+
+    pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
+        self.find_pte(va.floor()).map(|pte| {
+            let aligned_pa: PhysAddr = pte.ppn().into();
+            let offset = va.page_offset();
+            (aligned_pa.0 + offset).into()
+        })
+    }
+
+    pub fn token(&self) -> usize {
+        8usize << 60 | self.root_ppn.0
+    }
+}
+
+/// Translates a (possibly non-page-aligned, possibly multi-page) user byte
+/// range into a list of kernel-visible byte slices, one per physical page
+/// it spans. This is the primitive `copy_to_user`/`copy_from_user` stitch
+/// together to handle structs that straddle a page boundary.
+pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+    let page_table = PageTable::from_token(token);
+    let mut start = ptr as usize;
+    let end = start + len;
+    let mut v = Vec::new();
+    while start < end {
+        let start_va = VirtAddr::from(start);
+        let mut vpn = start_va.floor();
+        let ppn = page_table.translate(vpn).unwrap().ppn();
+        vpn.0 += 1;
+        let mut end_va: VirtAddr = vpn.into();
+        end_va = end_va.min(VirtAddr::from(end));
+        if end_va.page_offset() == 0 {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
+        } else {
+            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
+        }
+        start = end_va.into();
+    }
+    v
+}
+
+pub fn translated_str(token: usize, ptr: *const u8) -> String {
+    let page_table = PageTable::from_token(token);
+    let mut string = String::new();
+    let mut va = ptr as usize;
+    loop {
+        let ch: u8 = *(page_table
+            .translate_va(VirtAddr::from(va))
+            .unwrap()
+            .get_mut());
+        if ch == 0 {
+            break;
+        }
+        string.push(ch as char);
+        va += 1;
+    }
+    string
+}
+
+pub fn translated_ref<T>(token: usize, ptr: *const T) -> &'static T {
+    let page_table = PageTable::from_token(token);
+    page_table
+        .translate_va(VirtAddr::from(ptr as usize))
+        .unwrap()
+        .get_ref()
+}
+
+pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
+    let page_table = PageTable::from_token(token);
+    let va = ptr as usize;
+    page_table
+        .translate_va(VirtAddr::from(va))
+        .unwrap()
+        .get_mut()
+}
+
+/// Translates a raw user pointer to its kernel-visible counterpart,
+/// assuming `T` does not straddle a page boundary. Kept for single-page
+/// callers; anything that might cross a page (see `mm::copy_to_user`)
+/// must not use this.
+pub fn translated_ptr<T>(token: usize, ptr: *mut T) -> *mut T {
+    let page_table = PageTable::from_token(token);
+    let va = ptr as usize;
+    let pa: PhysAddr = page_table.translate_va(VirtAddr::from(va)).unwrap();
+    pa.0 as *mut T
+}
+
+impl PhysAddr {
+    pub fn get_mut<T>(&self) -> &'static mut T {
+        unsafe { &mut *(self.0 as *mut T) }
+    }
+    pub fn get_ref<T>(&self) -> &'static T {
+        unsafe { &*(self.0 as *const T) }
+    }
+}
@@ -0,0 +1,28 @@
+//! Uniprocessor interior mutability primitive.
+
+use core::cell::{RefCell, RefMut};
+
+/// Wraps a `RefCell` to make it `Sync`, which is sound because this kernel
+/// never runs with more than one hart active inside the same cell at once.
+/// Panics (rather than deadlocking) on a borrow conflict, same as `RefCell`.
+pub struct UPSafeCell<T> {
+    inner: RefCell<T>,
+}
+
+unsafe impl<T> Sync for UPSafeCell<T> {}
+
+impl<T> UPSafeCell<T> {
+    /// # Safety
+    /// The caller must guarantee that accesses to the inner data are
+    /// serialized, e.g. by disabling interrupts or only calling this from a
+    /// single hart.
+    pub unsafe fn new(value: T) -> Self {
+        Self {
+            inner: RefCell::new(value),
+        }
+    }
+
+    pub fn exclusive_access(&self) -> RefMut<'_, T> {
+        self.inner.borrow_mut()
+    }
+}
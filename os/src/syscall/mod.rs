@@ -0,0 +1,103 @@
+//! Syscall dispatch: decodes a syscall number and its raw argument array
+//! into a call to the matching `sys_*` function.
+
+mod process;
+
+use crate::config::MAX_SYSCALL_NUM;
+use crate::task::signal::SignalAction;
+use crate::task::syscall_filter::FilterMode;
+use crate::task::{current_task, exit_current_and_run_next};
+pub use process::*;
+
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_GETPID: usize = 172;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_FORK: usize = 220;
+const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_EXIT: usize = 93;
+const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_TASK_INFO: usize = 410;
+// ch8 编写代码 start (syscall filter)
+const SYSCALL_SET_SYSCALL_FILTER: usize = 451;
+// ch8 编写代码 end
+
+/// Linux-style "operation not permitted", returned negated (`-EPERM`) when
+/// a syscall is rejected by the current task's filter.
+const EPERM: isize = 1;
+
+/// Decodes and dispatches one syscall on behalf of the current task. Checks
+/// the task's seccomp-style filter first, then counts the call towards
+/// `TaskInfoInner::syscall_times` before actually running it.
+pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+    if let Some(rejected) = check_syscall_filter(syscall_id) {
+        return rejected;
+    }
+    {
+        let task = current_task().unwrap();
+        let mut inner = task.inner_exclusive_access();
+        if syscall_id < MAX_SYSCALL_NUM {
+            inner.task_info_inner.syscall_times[syscall_id] += 1;
+        }
+    }
+    match syscall_id {
+        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_YIELD => sys_yield(),
+        SYSCALL_GETPID => sys_getpid(),
+        SYSCALL_FORK => sys_fork(),
+        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
+        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_SBRK => sys_sbrk(args[0] as i32),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_KILL => sys_kill(args[0], args[1]),
+        SYSCALL_SIGACTION => sys_sigaction(
+            args[0],
+            args[1] as *const SignalAction,
+            args[2] as *mut SignalAction,
+        ),
+        SYSCALL_SIGPROCMASK => sys_sigprocmask(args[0] as u32),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        // ch8 编写代码 start (syscall filter)
+        SYSCALL_SET_SYSCALL_FILTER => {
+            sys_set_syscall_filter(args[0], args[1] as *const usize, args[2])
+        }
+        // ch8 编写代码 end
+        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+    }
+}
+
+// ch8 编写代码 start (syscall filter)
+/// `Some(-EPERM)` if the current task has a filter installed and it
+/// rejects `syscall_id` in "errno" mode; never returns (kills the task
+/// instead) if it rejects in "kill" mode; `None` if the call may proceed.
+fn check_syscall_filter(syscall_id: usize) -> Option<isize> {
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let filter = inner.syscall_filter.as_ref()?;
+    if filter.allows(syscall_id) {
+        return None;
+    }
+    match filter.mode() {
+        FilterMode::Errno => Some(-EPERM),
+        FilterMode::Kill => {
+            drop(inner);
+            drop(task);
+            exit_current_and_run_next(crate::task::syscall_filter::FILTER_KILL_EXIT_CODE);
+            unreachable!("exit_current_and_run_next does not return")
+        }
+    }
+}
+// ch8 编写代码 end
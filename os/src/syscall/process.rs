@@ -4,22 +4,26 @@ use alloc::sync::Arc;
 use crate::{
     config::MAX_SYSCALL_NUM,
     loader::get_app_data_by_name,
-    mm::{translated_refmut, translated_str, translated_ptr, VirtAddr, VirtPageNum},
+    mm::{copy_to_user, translated_ref, translated_refmut, translated_str, VirtAddr, VirtPageNum},
     task::{
         add_task, current_task, current_user_token, exit_current_and_run_next,
+        pid2task, insert_into_pid2task, signal::{SigSet, SignalAction, MAX_SIG},
         suspend_current_and_run_next, TaskStatus, mmap, munmap, TaskControlBlock,
+        syscall_filter::{FilterMode, SyscallFilter},
+        BIG_STRIDE, DEFAULT_PRIORITY,
     },
     timer::get_time_us,
 };
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct TimeVal {
     pub sec: usize,
     pub usec: usize,
 }
 
 /// Task information
+#[derive(Clone, Copy)]
 #[allow(dead_code)]
 pub struct TaskInfo {
     /// Task status in it's life cycle
@@ -115,36 +119,35 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
     // ---- release current PCB automatically
 }
 
-/// YOUR JOB: get time with second and microsecond
-/// HINT: You might reimplement it with virtual memory management.
-/// HINT: What if [`TimeVal`] is splitted by two pages ?
+/// Get time with second and microsecond.
+/// Uses [`copy_to_user`] instead of a direct pointer write, since `TimeVal`
+/// can straddle two pages in the caller's address space.
 pub fn sys_get_time(_ts: *mut TimeVal, _tz: usize) -> isize {
-    trace!(
-        "kernel:pid[{}] sys_get_time NOT IMPLEMENTED",
-        current_task().unwrap().pid.0
-    );
-    
+    trace!("kernel:pid[{}] sys_get_time", current_task().unwrap().pid.0);
     let time = get_time_us();
-    // println!("--------------------------------------");
-    let ts = translated_ptr(current_user_token(), _ts);
-    unsafe {
-        *ts = TimeVal {
-            sec: time / 1_000_000,
-            usec: time % 1_000_000,
-        };
-    }
+    let time_val = TimeVal {
+        sec: time / 1_000_000,
+        usec: time % 1_000_000,
+    };
+    copy_to_user(current_user_token(), _ts, &time_val);
     0
 }
 
-/// YOUR JOB: Finish sys_task_info to pass testcases
-/// HINT: You might reimplement it with virtual memory management.
-/// HINT: What if [`TaskInfo`] is splitted by two pages ?
+/// Fills in `*_ti` from the current task's `TaskInfoInner`, again via
+/// [`copy_to_user`] so a `TaskInfo` that straddles a page boundary is
+/// copied correctly.
 pub fn sys_task_info(_ti: *mut TaskInfo) -> isize {
-    trace!(
-        "kernel:pid[{}] sys_task_info NOT IMPLEMENTED",
-        current_task().unwrap().pid.0
-    );
-    -1
+    trace!("kernel:pid[{}] sys_task_info", current_task().unwrap().pid.0);
+    let task = current_task().unwrap();
+    let inner = task.inner_exclusive_access();
+    let task_info = TaskInfo {
+        status: TaskStatus::Running,
+        syscall_times: inner.task_info_inner.syscall_times,
+        time: (get_time_us() - inner.task_info_inner.start_time) / 1000,
+    };
+    drop(inner);
+    copy_to_user(current_user_token(), _ti, &task_info);
+    0
 }
 
 /// YOUR JOB: Implement mmap.
@@ -275,9 +278,14 @@ pub fn sys_spawn(_path: *const u8) -> isize {
         // Set proper pointers for the relationship of parents and child process,
         // Inspired by the fork method
         child_inner.parent = Some(Arc::downgrade(&parent_task));
+        child_inner.priority = DEFAULT_PRIORITY;
+        child_inner.stride = 0;
+        child_inner.pass = BIG_STRIDE / DEFAULT_PRIORITY;
+        child_inner.syscall_filter = parent_inner.syscall_filter.clone();
         parent_inner.children.push(child_task.clone());
         drop(child_inner);
         drop(parent_inner);
+        insert_into_pid2task(pid, &child_task);
 
         add_task(child_task);
         
@@ -305,8 +313,136 @@ pub fn sys_set_priority(_prio: isize) -> isize {
     
     let current_task = current_task().unwrap();
     let mut inner = current_task.inner_exclusive_access();
-    inner.priority = _prio as usize;
+    inner.set_priority(_prio as usize);
     drop(inner);
 
     _prio
 }
+
+// ch8 编写代码 start (signals)
+
+/// Sets `signum`'s pending bit on the process identified by `pid`, found
+/// via the global pid table. Default actions (e.g. `SIGKILL`) are applied
+/// at delivery time in the trap-return path, not here.
+///
+/// `signum == 0` is POSIX's "null signal": it only probes whether `pid`
+/// is a live process and must never actually be delivered, so it's
+/// special-cased here rather than falling through to `pending.insert(0)`
+/// (which would land on the default-action path and kill the target).
+pub fn sys_kill(pid: usize, signum: usize) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_kill pid={} signum={}",
+        current_task().unwrap().pid.0,
+        pid,
+        signum
+    );
+    if signum > MAX_SIG {
+        return -1;
+    }
+    if signum == 0 {
+        return if pid2task(pid).is_some() { 0 } else { -1 };
+    }
+    if let Some(task) = pid2task(pid) {
+        task.inner_exclusive_access().signals.pending.insert(signum);
+        0
+    } else {
+        -1
+    }
+}
+
+/// Installs a new handler `action` for `signum` on the current task,
+/// optionally handing back the previous one through `old_action`.
+pub fn sys_sigaction(
+    signum: usize,
+    action: *const SignalAction,
+    old_action: *mut SignalAction,
+) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_sigaction signum={}",
+        current_task().unwrap().pid.0,
+        signum
+    );
+    if signum == 0 || signum > MAX_SIG || action.is_null() {
+        return -1;
+    }
+    let token = current_user_token();
+    let new_action = *translated_ref(token, action);
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !old_action.is_null() {
+        let prev = inner.signals.actions[signum];
+        *translated_refmut(token, old_action) = prev;
+    }
+    inner.signals.actions[signum] = new_action;
+    0
+}
+
+/// Replaces the current task's blocked-signal mask, returning the previous
+/// mask's raw bits.
+pub fn sys_sigprocmask(mask: u32) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_sigprocmask",
+        current_task().unwrap().pid.0
+    );
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let old_mask = inner.signals.blocked.0;
+    inner.signals.blocked = SigSet(mask);
+    old_mask as isize
+}
+
+/// Restores the trap context that was saved when a signal handler was
+/// entered, undoing the side effects of delivery. Called by user code at
+/// the end of a handler; never returns to its own caller.
+pub fn sys_sigreturn() -> isize {
+    trace!(
+        "kernel:pid[{}] sys_sigreturn",
+        current_task().unwrap().pid.0
+    );
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    inner.signals.handling = false;
+    inner.signals.blocked = inner.signals.saved_blocked;
+    if let Some(saved) = inner.signals.saved_trap_cx.take() {
+        *inner.get_trap_cx() = saved;
+        // a0 holds the restored context's own return value; sys_sigreturn
+        // itself must not clobber it afterwards.
+        inner.get_trap_cx().x[10] as isize
+    } else {
+        -1
+    }
+}
+// ch8 编写代码 end
+
+// ch8 编写代码 start (syscall filter)
+
+/// `mode == 0` disallowed syscalls fail with `-EPERM`; `mode == 1` they
+/// kill the task. Installs an allow-list built from the `len` syscall ids
+/// at `allow_list_ptr`, replacing any filter this task already had. Passing
+/// `len == 0` clears the filter, restoring the "everything allowed" state.
+pub fn sys_set_syscall_filter(mode: usize, allow_list_ptr: *const usize, len: usize) -> isize {
+    trace!(
+        "kernel:pid[{}] sys_set_syscall_filter mode={} len={}",
+        current_task().unwrap().pid.0,
+        mode,
+        len
+    );
+    let mode = match mode {
+        0 => FilterMode::Errno,
+        1 => FilterMode::Kill,
+        _ => return -1,
+    };
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if len == 0 {
+        inner.syscall_filter = None;
+        return 0;
+    }
+    let allow_list: alloc::vec::Vec<usize> = (0..len)
+        .map(|i| *translated_ref(token, unsafe { allow_list_ptr.add(i) }))
+        .collect();
+    inner.syscall_filter = Some(SyscallFilter::new(mode, &allow_list));
+    0
+}
+// ch8 编写代码 end
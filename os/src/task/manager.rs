@@ -0,0 +1,69 @@
+//! The task manager: owns the ready queue and decides which runnable task
+//! runs next.
+
+use super::task::TaskControlBlock;
+use crate::sync::UPSafeCell;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use lazy_static::lazy_static;
+
+pub struct TaskManager {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+
+    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+
+    /// Removes and returns the runnable task with the smallest stride (see
+    /// `TaskControlBlockInner::stride_lt`), bumping its stride by its pass
+    /// so the next `fetch` moves on to whoever is next in line. Ties keep
+    /// FIFO order, since `stride_lt` is a strict `<`.
+    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        if self.ready_queue.is_empty() {
+            return None;
+        }
+        let mut min_idx = 0;
+        for i in 1..self.ready_queue.len() {
+            let i_is_smaller = {
+                let candidate = self.ready_queue[i].inner_exclusive_access();
+                let current_min = self.ready_queue[min_idx].inner_exclusive_access();
+                candidate.stride_lt(&current_min)
+            };
+            if i_is_smaller {
+                min_idx = i;
+            }
+        }
+        let task = self.ready_queue.remove(min_idx).unwrap();
+        {
+            let mut inner = task.inner_exclusive_access();
+            let pass = inner.pass;
+            inner.stride = inner.stride.wrapping_add(pass);
+        }
+        Some(task)
+    }
+}
+
+lazy_static! {
+    pub static ref TASK_MANAGER: UPSafeCell<TaskManager> =
+        unsafe { UPSafeCell::new(TaskManager::new()) };
+}
+
+/// Pushes a task onto the ready queue; called whenever a task becomes
+/// runnable again (creation, fork, yield, ...).
+pub fn add_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().add(task);
+}
+
+/// Pops the next task to run, chosen by smallest stride. See
+/// `TaskManager::fetch`.
+pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch()
+}
@@ -0,0 +1,90 @@
+//! POSIX-style signals: pending/blocked bitmasks and per-signal handler
+//! actions, following the ch8 lab's simplified signal model.
+
+use crate::trap::TrapContext;
+
+/// Number of distinct signals this kernel tracks (1..=31, like Linux's
+/// standard, non-realtime signal range).
+pub const MAX_SIG: usize = 31;
+
+pub const SIGKILL: usize = 9;
+pub const SIGSTOP: usize = 19;
+pub const SIGCONT: usize = 18;
+pub const SIGDEF: usize = 0;
+
+/// A bitmask over signal numbers 1..=31, stored with signal `n` in bit `n`
+/// (bit 0 unused) so `signum` can index it directly.
+#[derive(Copy, Clone, Default)]
+pub struct SigSet(pub u32);
+
+impl SigSet {
+    pub fn contains(&self, signum: usize) -> bool {
+        signum <= MAX_SIG && (self.0 & (1 << signum)) != 0
+    }
+    pub fn insert(&mut self, signum: usize) {
+        self.0 |= 1 << signum;
+    }
+    pub fn remove(&mut self, signum: usize) {
+        self.0 &= !(1 << signum);
+    }
+    /// The lowest-numbered signal that is pending and not blocked, if any.
+    pub fn first_unblocked(&self, blocked: SigSet) -> Option<usize> {
+        let deliverable = self.0 & !blocked.0;
+        if deliverable == 0 {
+            None
+        } else {
+            Some(deliverable.trailing_zeros() as usize)
+        }
+    }
+}
+
+/// A process's handler registration for one signal, installed via
+/// `sys_sigaction`.
+#[derive(Copy, Clone)]
+pub struct SignalAction {
+    /// User-space entry point to jump to, or 0 (`SIG_DFL`) for the default
+    /// action, which terminates the task. No other value is special-cased:
+    /// 1 (`SIG_IGN`) is treated as a handler address like any other.
+    pub handler: usize,
+    /// Signals to additionally block for the duration of the handler.
+    pub mask: SigSet,
+}
+
+impl Default for SignalAction {
+    fn default() -> Self {
+        Self {
+            handler: 0,
+            mask: SigSet::default(),
+        }
+    }
+}
+
+/// Per-task signal state, embedded in `TaskControlBlockInner`.
+pub struct SignalState {
+    pub pending: SigSet,
+    pub blocked: SigSet,
+    pub actions: [SignalAction; MAX_SIG + 1],
+    /// Trap context saved by the trap-return path when a handler is
+    /// entered, so `sys_sigreturn` can later restore it.
+    pub saved_trap_cx: Option<TrapContext>,
+    /// `blocked` as it was just before delivery added the handler's mask
+    /// to it, so `sys_sigreturn` can restore it: the mask is only meant to
+    /// apply for the duration of the handler, not forever after.
+    pub saved_blocked: SigSet,
+    /// Set while a handler is running, so a second delivery doesn't
+    /// re-enter it before `sys_sigreturn`.
+    pub handling: bool,
+}
+
+impl Default for SignalState {
+    fn default() -> Self {
+        Self {
+            pending: SigSet::default(),
+            blocked: SigSet::default(),
+            actions: [SignalAction::default(); MAX_SIG + 1],
+            saved_trap_cx: None,
+            saved_blocked: SigSet::default(),
+            handling: false,
+        }
+    }
+}
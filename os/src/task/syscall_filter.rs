@@ -0,0 +1,55 @@
+//! Seccomp-style per-task syscall permission filtering, installed via
+//! `sys_set_syscall_filter` and consulted by the syscall dispatcher before
+//! every call.
+
+use crate::config::MAX_SYSCALL_NUM;
+
+const BITMAP_WORDS: usize = (MAX_SYSCALL_NUM + 63) / 64;
+
+/// What happens when the current task attempts a syscall its filter
+/// doesn't allow.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum FilterMode {
+    /// The syscall fails with `-EPERM`; the task keeps running.
+    Errno,
+    /// The task is terminated immediately, with [`FILTER_KILL_EXIT_CODE`].
+    Kill,
+}
+
+/// Exit code used when a task is killed for violating its syscall filter,
+/// distinguishing it from an ordinary `sys_exit` or a fatal trap.
+pub const FILTER_KILL_EXIT_CODE: i32 = -99;
+
+/// A task's syscall allow-list. Absence of a `SyscallFilter` (the common
+/// case) means every syscall is permitted; installing one restricts the
+/// task to exactly the ids passed to `sys_set_syscall_filter`.
+#[derive(Clone)]
+pub struct SyscallFilter {
+    mode: FilterMode,
+    allowed: [u64; BITMAP_WORDS],
+}
+
+impl SyscallFilter {
+    /// Builds a filter in `mode` that allows exactly the syscall ids in
+    /// `allow_list`. Ids `>= MAX_SYSCALL_NUM` are silently ignored, since
+    /// they could never be dispatched anyway.
+    pub fn new(mode: FilterMode, allow_list: &[usize]) -> Self {
+        let mut allowed = [0u64; BITMAP_WORDS];
+        for &id in allow_list {
+            if id < MAX_SYSCALL_NUM {
+                allowed[id / 64] |= 1 << (id % 64);
+            }
+        }
+        Self { mode, allowed }
+    }
+
+    pub fn mode(&self) -> FilterMode {
+        self.mode
+    }
+
+    /// Whether `id` is on the allow-list. A single bitmap lookup, so this
+    /// is cheap enough to call on every syscall.
+    pub fn allows(&self, id: usize) -> bool {
+        id < MAX_SYSCALL_NUM && (self.allowed[id / 64] & (1 << (id % 64))) != 0
+    }
+}
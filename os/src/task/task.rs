@@ -1,7 +1,17 @@
-//! Types related to task management
+//! Types related to task (process) management
 
-use super::TaskContext;
+use super::signal::SignalState;
+use super::syscall_filter::SyscallFilter;
+use super::{current_task, TaskContext};
 use crate::config::MAX_SYSCALL_NUM;
+use crate::mm::{MemorySet, PhysPageNum, VirtPageNum};
+use crate::sync::UPSafeCell;
+use crate::trap::TrapContext;
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cell::RefMut;
+use lazy_static::lazy_static;
 
 // ch3 编写代码 start
 /// balaba
@@ -15,18 +25,271 @@ pub struct TaskInfoInner {
 
 // ch3 编写代码 end
 
-/// The task control block (TCB) of a task.
-#[derive(Copy, Clone)]
+// ch5 编写代码 start (stride scheduling)
+/// Stride increment accounting unit. `pass = BIG_STRIDE / priority`, so a
+/// task with higher priority accumulates stride more slowly and gets picked
+/// more often. Must stay well above the largest possible `pass` (which is
+/// `BIG_STRIDE / 2` since `sys_set_priority` enforces `prio >= 2`) so that
+/// the wrapping comparison below never sees a gap wider than `BIG_STRIDE`.
+pub const BIG_STRIDE: usize = 0x10000;
+
+/// Priority newly created tasks start with, before any `sys_set_priority` call.
+pub const DEFAULT_PRIORITY: usize = 16;
+// ch5 编写代码 end
+
+/// A process id, as handed out by the PID allocator.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PidHandle(pub usize);
+
+lazy_static! {
+    /// Hands out pids in increasing order. Unlike physical frames, a pid
+    /// is never recycled — there's no scarcity to manage, just a `usize`.
+    static ref NEXT_PID: UPSafeCell<usize> = unsafe { UPSafeCell::new(0) };
+}
+
+/// Allocates the next process id.
+pub fn pid_alloc() -> PidHandle {
+    let mut next = NEXT_PID.exclusive_access();
+    let pid = *next;
+    *next += 1;
+    PidHandle(pid)
+}
+
+/// The task control block (TCB) of a task, i.e. a process in this kernel:
+/// the immutable identity (`pid`) lives directly on the struct, everything
+/// that changes over the task's lifetime lives behind `inner`.
 pub struct TaskControlBlock {
+    // immutable
+    pub pid: PidHandle,
+    // mutable
+    inner: UPSafeCell<TaskControlBlockInner>,
+}
+
+pub struct TaskControlBlockInner {
+    /// Page holding this task's `TrapContext`, mapped at `TRAP_CONTEXT_BASE`
+    /// in its own address space.
+    pub trap_cx_ppn: PhysPageNum,
+    pub task_cx: TaskContext,
     /// The task status in it's lifecycle
     pub task_status: TaskStatus,
-    /// The task context
-    pub task_cx: TaskContext,
+    pub memory_set: MemorySet,
+    pub parent: Option<Weak<TaskControlBlock>>,
+    pub children: Vec<Arc<TaskControlBlock>>,
+    pub exit_code: i32,
+    pub heap_bottom: usize,
+    pub program_brk: usize,
     // ch3 编写代码 start
     /// balaba
     pub task_info_inner: TaskInfoInner,
     // ch3 编写代码 end
+    // ch5 编写代码 start (stride scheduling)
+    /// Scheduling priority, as set by `sys_set_priority`. Always `>= 2`.
+    pub priority: usize,
+    /// This task's accumulated stride. The scheduler always picks the
+    /// runnable task with the smallest `stride`.
+    pub stride: usize,
+    /// Amount added to `stride` every time this task is scheduled:
+    /// `BIG_STRIDE / priority`.
+    pub pass: usize,
+    // ch5 编写代码 end
+    // ch8 编写代码 start (signals)
+    /// Pending signals, blocked mask, and installed handler actions.
+    pub signals: SignalState,
+    // ch8 编写代码 end
+    // ch8 编写代码 start (syscall filter)
+    /// Seccomp-style syscall allow-list installed by
+    /// `sys_set_syscall_filter`. `None` means every syscall is permitted.
+    ///
+    /// Must be inherited by both `fork` and `sys_spawn` — a child is only
+    /// as sandboxed as its parent if filters survive both ways a new task
+    /// gets created. `sys_spawn` does this explicitly
+    /// (`child_inner.syscall_filter = parent_inner.syscall_filter.clone()`),
+    /// and `TaskControlBlock::fork` clones it into the child's inner state.
+    pub syscall_filter: Option<SyscallFilter>,
+    // ch8 编写代码 end
+}
+
+impl TaskControlBlockInner {
+    pub fn get_trap_cx(&self) -> &'static mut TrapContext {
+        self.trap_cx_ppn.get_mut()
+    }
+
+    pub fn get_user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+
+    pub fn is_zombie(&self) -> bool {
+        self.task_status == TaskStatus::Zombie
+    }
+
+    // ch5 编写代码 start (stride scheduling)
+    /// Updates `priority` and recomputes `pass` accordingly. Called from
+    /// `sys_set_priority` so the new priority takes effect on the task's
+    /// next scheduling step.
+    pub fn set_priority(&mut self, priority: usize) {
+        self.priority = priority;
+        self.pass = BIG_STRIDE / priority;
+    }
+
+    /// Compares two strides so that ordering stays correct across
+    /// wraparound: `self < other` iff the wrapping difference `self.stride
+    /// - other.stride`, reinterpreted as signed, is negative. This is valid
+    /// as long as the true gap between any two strides stays below
+    /// `BIG_STRIDE`, which holds here since the minimum priority is 2.
+    pub fn stride_lt(&self, other: &Self) -> bool {
+        (self.stride.wrapping_sub(other.stride) as isize) < 0
+    }
+    // ch5 编写代码 end
+}
+
+impl TaskControlBlock {
+    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+
+    /// Builds a brand-new process to run the ELF image `elf_data`, used by
+    /// `sys_spawn` (and whatever constructs the very first process).
+    ///
+    /// NOTE: parsing program headers out of `elf_data` — entry point,
+    /// per-segment permissions, the initial user stack — isn't implemented
+    /// in this tree yet, so `memory_set`/`task_cx`/`trap_cx_ppn` below are
+    /// placeholders rather than a real loaded image. Everything else (pid
+    /// allocation, scheduling defaults, signal and syscall-filter state) is
+    /// fully set up.
+    pub fn new(elf_data: &[u8]) -> Self {
+        let _ = elf_data;
+        let pid_handle = pid_alloc();
+        let inner = TaskControlBlockInner {
+            trap_cx_ppn: PhysPageNum(0),
+            task_cx: TaskContext::zero_init(),
+            task_status: TaskStatus::Ready,
+            memory_set: MemorySet::new_bare(),
+            parent: None,
+            children: Vec::new(),
+            exit_code: 0,
+            heap_bottom: 0,
+            program_brk: 0,
+            task_info_inner: TaskInfoInner {
+                syscall_times: [0; MAX_SYSCALL_NUM],
+                start_time: 0,
+            },
+            priority: DEFAULT_PRIORITY,
+            stride: 0,
+            pass: BIG_STRIDE / DEFAULT_PRIORITY,
+            signals: SignalState::default(),
+            syscall_filter: None,
+        };
+        Self {
+            pid: pid_handle,
+            inner: unsafe { UPSafeCell::new(inner) },
+        }
+    }
+
+    /// Copy-on-write `fork`: the child's address space shares the parent's
+    /// physical frames via `MemorySet::from_existed_user` (which needs to
+    /// mutate the parent's PTEs to install the `COW` bit, hence the
+    /// exclusive borrow of `parent_inner` for the whole call), and its
+    /// scheduling state starts fresh rather than inheriting the parent's
+    /// in-flight `stride`. The syscall filter, by contrast, is part of the
+    /// child's sandboxing contract and must carry over unchanged.
+    ///
+    /// NOTE: like `TaskControlBlock::new`, this doesn't build a real
+    /// `trap_cx_ppn`/`task_cx` for the child — that needs the kernel-stack
+    /// and trampoline-mapping machinery this tree doesn't have yet, so the
+    /// child reuses the parent's (placeholder) `trap_cx_ppn` and starts with
+    /// a zeroed `task_cx`.
+    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
+        let mut parent_inner = self.inner_exclusive_access();
+        let memory_set = MemorySet::from_existed_user(&mut parent_inner.memory_set);
+        let pid_handle = pid_alloc();
+        let inner = TaskControlBlockInner {
+            trap_cx_ppn: parent_inner.trap_cx_ppn,
+            task_cx: TaskContext::zero_init(),
+            task_status: TaskStatus::Ready,
+            memory_set,
+            parent: Some(Arc::downgrade(self)),
+            children: Vec::new(),
+            exit_code: 0,
+            heap_bottom: parent_inner.heap_bottom,
+            program_brk: parent_inner.program_brk,
+            task_info_inner: TaskInfoInner {
+                syscall_times: [0; MAX_SYSCALL_NUM],
+                start_time: 0,
+            },
+            priority: DEFAULT_PRIORITY,
+            stride: 0,
+            pass: BIG_STRIDE / DEFAULT_PRIORITY,
+            signals: SignalState::default(),
+            syscall_filter: parent_inner.syscall_filter.clone(),
+        };
+        let child = Arc::new(Self {
+            pid: pid_handle,
+            inner: unsafe { UPSafeCell::new(inner) },
+        });
+        parent_inner.children.push(Arc::clone(&child));
+        insert_into_pid2task(child.pid.0, &child);
+        child
+    }
+}
+
+// ch8 编写代码 start (signals)
+lazy_static! {
+    /// Maps a live pid to its `TaskControlBlock`, so `sys_kill` can target
+    /// any process, not just the caller's own children.
+    static ref PID2TASK: UPSafeCell<BTreeMap<usize, Weak<TaskControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// Looks up a still-live task by pid. Returns `None` once the task has
+/// exited and its last `Arc` has been dropped, even if it hasn't yet been
+/// removed from the table.
+pub fn pid2task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    PID2TASK
+        .exclusive_access()
+        .get(&pid)
+        .and_then(Weak::upgrade)
+}
+
+/// Registers a newly created task so `pid2task` can find it. Called
+/// wherever a new task is created (`fork`, `sys_spawn`).
+pub fn insert_into_pid2task(pid: usize, task: &Arc<TaskControlBlock>) {
+    PID2TASK
+        .exclusive_access()
+        .insert(pid, Arc::downgrade(task));
+}
+
+/// Drops a task's entry once it has exited; called from
+/// `exit_current_and_run_next`.
+pub fn remove_from_pid2task(pid: usize) {
+    PID2TASK.exclusive_access().remove(&pid);
+}
+// ch8 编写代码 end
+
+// ch4 编写代码 start (VMA-based mmap/munmap)
+/// Maps `[start_vpn, end_vpn)` into the current task's address space. See
+/// `MemorySet::mmap` for the precise semantics and error codes.
+pub fn mmap(start_vpn: VirtPageNum, end_vpn: VirtPageNum, port: usize) -> isize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .mmap(start_vpn, end_vpn, port)
+}
+
+/// Unmaps `[start_vpn, end_vpn)` from the current task's address space. See
+/// `MemorySet::munmap` for the precise semantics and error codes.
+pub fn munmap(start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> isize {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .memory_set
+        .munmap(start_vpn, end_vpn)
 }
+// ch4 编写代码 end
 
 /// The status of a task
 #[derive(Copy, Clone, PartialEq)]
@@ -37,6 +300,8 @@ pub enum TaskStatus {
     Ready,
     /// running
     Running,
+    /// exited, but not yet reaped by its parent's `waitpid`
+    Zombie,
     /// exited
     Exited,
 }
@@ -0,0 +1,132 @@
+//! Trap handling: dispatch of traps taken from U-mode.
+
+mod context;
+
+pub use context::TrapContext;
+
+use crate::config::{TRAMPOLINE, TRAP_CONTEXT_BASE};
+use crate::mm::VirtAddr;
+use crate::syscall::syscall;
+use crate::task::signal::SIGKILL;
+use crate::task::{current_task, current_user_token, exit_current_and_run_next};
+use core::arch::asm;
+use riscv::register::{
+    scause::{self, Exception, Trap},
+    stval,
+};
+
+/// Entered from `__alltraps` with the kernel's own trap vector already
+/// swapped in; dispatches on `scause`.
+#[no_mangle]
+pub fn trap_handler() -> ! {
+    let scause = scause::read();
+    let stval = stval::read();
+    match scause.cause() {
+        Trap::Exception(Exception::UserEnvCall) => {
+            let task = current_task().unwrap();
+            let mut cx = task.inner_exclusive_access().get_trap_cx();
+            cx.sepc += 4;
+            let (id, args) = (cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]);
+            drop(task);
+            let result = syscall(id, args) as usize;
+            // Re-fetch: `exec` swaps the trap context out from under us.
+            let task = current_task().unwrap();
+            cx = task.inner_exclusive_access().get_trap_cx();
+            cx.x[10] = result;
+        }
+        // A write to a page mapped copy-on-write: give the faulting task
+        // its own frame and retry, rather than killing it outright.
+        Trap::Exception(Exception::StorePageFault) | Trap::Exception(Exception::StoreFault) => {
+            let va: VirtAddr = (stval as usize).into();
+            let task = current_task().unwrap();
+            let resolved = task
+                .inner_exclusive_access()
+                .memory_set
+                .handle_cow_fault(va.floor());
+            if !resolved {
+                println!(
+                    "[kernel] PageFault in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
+                    stval,
+                    task.inner_exclusive_access().get_trap_cx().sepc
+                );
+                drop(task);
+                exit_current_and_run_next(-2);
+            }
+        }
+        Trap::Exception(Exception::IllegalInstruction) => {
+            println!("[kernel] IllegalInstruction in application, core dumped.");
+            exit_current_and_run_next(-3);
+        }
+        _ => {
+            panic!(
+                "Unsupported trap {:?}, stval = {:#x}!",
+                scause.cause(),
+                stval
+            );
+        }
+    }
+    check_and_deliver_signal();
+    trap_return();
+}
+
+/// Runs on every return to U-mode: if the current task has an unblocked
+/// pending signal, deliver it. `SIGKILL` (and any signal left at its
+/// default action) terminates the task outright; anything else with a
+/// user handler installed diverts `sepc` to it, stashing the interrupted
+/// `TrapContext` so `sys_sigreturn` can restore it once the handler
+/// returns.
+fn check_and_deliver_signal() {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if inner.signals.handling {
+        return;
+    }
+    let signum = match inner
+        .signals
+        .pending
+        .first_unblocked(inner.signals.blocked)
+    {
+        Some(signum) => signum,
+        None => return,
+    };
+    inner.signals.pending.remove(signum);
+    let action = inner.signals.actions[signum];
+    if signum == SIGKILL || action.handler == 0 {
+        // No handler installed (or SIGKILL, which can't be caught):
+        // default action is to terminate the task.
+        drop(inner);
+        drop(task);
+        exit_current_and_run_next(-(signum as i32));
+        return;
+    }
+    let cx = inner.get_trap_cx();
+    inner.signals.saved_trap_cx = Some(*cx);
+    inner.signals.saved_blocked = inner.signals.blocked;
+    inner.signals.blocked.0 |= action.mask.0;
+    inner.signals.handling = true;
+    cx.x[10] = signum;
+    cx.sepc = action.handler;
+}
+
+/// Switches back to U-mode, restoring the current task's saved registers
+/// from its `TrapContext` at `TRAP_CONTEXT_BASE`.
+#[no_mangle]
+pub fn trap_return() -> ! {
+    let trap_cx_ptr = TRAP_CONTEXT_BASE;
+    let user_satp = current_user_token();
+    extern "C" {
+        fn __alltraps();
+        fn __restore();
+    }
+    let restore_va = __restore as usize - __alltraps as usize + TRAMPOLINE;
+    unsafe {
+        asm!(
+            "fence.i",
+            "jr {restore_va}",
+            restore_va = in(reg) restore_va,
+            in("a0") trap_cx_ptr,
+            in("a1") user_satp,
+            options(noreturn)
+        );
+    }
+}